@@ -0,0 +1,66 @@
+use std::time::{Duration, SystemTime};
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::ServerError;
+
+/// Default lifetime for an email confirmation token, if the caller doesn't
+/// need a different one.
+pub const DEFAULT_CONFIRMATION_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// A single-use, time-limited email confirmation token.
+///
+/// Only the SHA-256 digest of the token is kept; the plaintext is handed to
+/// the caller once, by `issue_confirmation_token`, and never stored.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfirmationToken {
+    token_hash: String,
+    expires_at: SystemTime,
+}
+
+impl ConfirmationToken {
+    /// Checks `candidate` against the stored hash and expiry. Does not
+    /// consume the token; the caller is responsible for invalidating it
+    /// (e.g. by removing it from wherever it keeps confirmation records).
+    pub fn verify(&self, candidate: &str) -> Result<(), ServerError> {
+        if SystemTime::now() > self.expires_at {
+            return Err(ServerError::ConfirmationTokenExpired);
+        }
+        if self.token_hash != hash_token(candidate) {
+            return Err(ServerError::InvalidConfirmationToken);
+        }
+        Ok(())
+    }
+}
+
+/// A freshly-issued confirmation token: the plaintext to hand to the user
+/// and the record to persist in its place.
+pub struct IssuedConfirmationToken {
+    pub token: String,
+    pub record: ConfirmationToken,
+}
+
+/// Generates a new single-use confirmation token valid for `ttl`.
+///
+/// Re-requesting a confirmation simply calls this again and overwrites the
+/// previous record, which rotates the token and invalidates the old one.
+pub fn issue_confirmation_token(ttl: Duration) -> IssuedConfirmationToken {
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    let record = ConfirmationToken {
+        token_hash: hash_token(&token),
+        expires_at: SystemTime::now() + ttl,
+    };
+    IssuedConfirmationToken { token, record }
+}
+
+fn hash_token(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,38 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand_core::OsRng;
+
+use crate::ServerError;
+
+// Cost parameters for the Argon2id KDF used to hash account passwords.
+// Tuned for an interactive login path rather than maximum hardness.
+const MEMORY_COST_KIB: u32 = 19_456;
+const TIME_COST: u32 = 2;
+const PARALLELISM: u32 = 1;
+
+fn argon2() -> Argon2<'static> {
+    let params = Params::new(MEMORY_COST_KIB, TIME_COST, PARALLELISM, None)
+        .expect("static Argon2id cost parameters are valid");
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// Hashes a plaintext password into a PHC-encoded Argon2id string, using a
+/// fresh random salt per call.
+pub fn hash_password(password: &str) -> Result<String, ServerError> {
+    let salt = SaltString::generate(&mut OsRng);
+    argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| ServerError::PasswordHash)
+}
+
+/// Verifies a plaintext candidate against a previously hashed PHC string, in
+/// constant time.
+pub fn verify_password(password_hash: &str, candidate: &str) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(password_hash) else {
+        return false;
+    };
+    argon2()
+        .verify_password(candidate.as_bytes(), &parsed_hash)
+        .is_ok()
+}
@@ -0,0 +1,103 @@
+use thiserror::Error;
+use tonic::{Code, Status};
+
+/// Errors produced by the `servercore` domain types.
+///
+/// Every validation failure in `User::create`, `Product::create` and
+/// `Business::create` is represented here instead of a bare `&'static str`,
+/// so gRPC handlers can map each variant to the `tonic::Status` code that
+/// actually describes it.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ServerError {
+    #[error("id is not a valid UUID")]
+    InvalidUuid,
+    #[error("email is not valid")]
+    InvalidEmail,
+    #[error("phone is not valid")]
+    InvalidPhone,
+    #[error("URL is not valid")]
+    InvalidUrl,
+    #[error("title is too short")]
+    TitleTooShort,
+    #[error("title is too long")]
+    TitleTooLong,
+    #[error("description is too short")]
+    DescriptionTooShort,
+    #[error("description is too long")]
+    DescriptionTooLong,
+    #[error("price could not be parsed")]
+    PriceParse,
+    #[error("currency code is not a known ISO 4217 currency")]
+    UnknownCurrency,
+    #[error("password could not be hashed")]
+    PasswordHash,
+    #[error("email or password is incorrect")]
+    InvalidCredentials,
+    #[error("token could not be signed")]
+    TokenEncode,
+    #[error("token is missing, malformed, expired or has a bad signature")]
+    InvalidToken,
+    #[error("confirmation token has expired")]
+    ConfirmationTokenExpired,
+    #[error("confirmation token is not valid")]
+    InvalidConfirmationToken,
+    #[error("date is not a valid RFC 3339 timestamp")]
+    InvalidDate,
+    #[error("expected date cannot be earlier than the ordered date")]
+    ExpectedDateBeforeOrderedDate,
+    #[error("order status is not recognized")]
+    InvalidOrderStatus,
+    #[error("that order status transition is not allowed")]
+    IllegalOrderTransition,
+    #[error("display name is too long")]
+    DisplayNameTooLong,
+    #[error("bio is too long")]
+    BioTooLong,
+    #[error("too many extra profile fields")]
+    TooManyExtraFields,
+    #[error("extra field key cannot be empty")]
+    ExtraFieldKeyEmpty,
+    #[error("extra field key is too long")]
+    ExtraFieldKeyTooLong,
+    #[error("extra field value is too long")]
+    ExtraFieldValueTooLong,
+    #[error("{0} was not found")]
+    NotFound(&'static str),
+    #[error("caller is missing the required '{0}' role")]
+    MissingRole(&'static str),
+}
+
+impl From<ServerError> for Status {
+    fn from(err: ServerError) -> Self {
+        let code = match err {
+            ServerError::PasswordHash | ServerError::TokenEncode => Code::Internal,
+            ServerError::InvalidCredentials
+            | ServerError::InvalidToken
+            | ServerError::ConfirmationTokenExpired
+            | ServerError::InvalidConfirmationToken => Code::Unauthenticated,
+            ServerError::IllegalOrderTransition => Code::FailedPrecondition,
+            ServerError::NotFound(_) => Code::NotFound,
+            ServerError::MissingRole(_) => Code::PermissionDenied,
+            ServerError::InvalidUuid
+            | ServerError::InvalidEmail
+            | ServerError::InvalidPhone
+            | ServerError::InvalidUrl
+            | ServerError::TitleTooShort
+            | ServerError::TitleTooLong
+            | ServerError::DescriptionTooShort
+            | ServerError::DescriptionTooLong
+            | ServerError::InvalidDate
+            | ServerError::ExpectedDateBeforeOrderedDate
+            | ServerError::InvalidOrderStatus
+            | ServerError::UnknownCurrency
+            | ServerError::PriceParse
+            | ServerError::DisplayNameTooLong
+            | ServerError::BioTooLong
+            | ServerError::TooManyExtraFields
+            | ServerError::ExtraFieldKeyEmpty
+            | ServerError::ExtraFieldKeyTooLong
+            | ServerError::ExtraFieldValueTooLong => Code::InvalidArgument,
+        };
+        Status::new(code, err.to_string())
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+use crate::ServerError;
+
+// Generates a UUID-v4-backed newtype ID. Parsing/deserializing validates the
+// UUID is well-formed *and* version 4, so a constructed value can never hold
+// a bad ID.
+macro_rules! uuid_id {
+    ($name:ident) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub struct $name(Uuid);
+
+        impl $name {
+            pub fn new_v4() -> Self {
+                $name(Uuid::new_v4())
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name(Uuid::new_v4())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ServerError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                let uuid = Uuid::parse_str(value).map_err(|_| ServerError::InvalidUuid)?;
+                if uuid.get_version_num() != 4 {
+                    return Err(ServerError::InvalidUuid);
+                }
+                Ok($name(uuid))
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = ServerError;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0.to_string())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let raw = String::deserialize(deserializer)?;
+                raw.parse().map_err(|_| DeError::custom("id is not a valid v4 UUID"))
+            }
+        }
+    };
+}
+
+uuid_id!(UserId);
+uuid_id!(BusinessId);
+uuid_id!(ProductId);
+uuid_id!(OrderId);
@@ -1,13 +1,35 @@
 use rusty_money::{iso, Money};
 use serde::{Deserialize, Serialize};
-use url::{ParseError, Url};
-use uuid::Uuid;
+use url::Url;
 use validator::{validate_email, validate_phone};
 use rayon::prelude::*;
 
+mod confirmation;
+mod credentials;
+mod error;
+mod ids;
+mod order;
+mod search;
+mod token;
+pub use confirmation::{
+    issue_confirmation_token, ConfirmationToken, IssuedConfirmationToken, DEFAULT_CONFIRMATION_TTL,
+};
+pub use error::ServerError;
+pub use ids::{BusinessId, OrderId, ProductId, UserId};
+pub use order::{Order, OrderStatus};
+pub use search::{InMemorySearchIndex, SearchIndex};
+pub use token::{issue_token, verify_token, Claims};
+
+/// A single custom name/value field on a `User`'s profile.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct ExtraField {
+    pub name: String,
+    pub value: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub struct User {
-    pub user_id: String, // UUID v4
+    pub user_id: UserId,
     pub email: String,
     pub phone: String,
     pub address: String,
@@ -15,11 +37,14 @@ pub struct User {
     pub password_hash: String,
     pub confirmed: bool,
     pub roles: Vec<String>,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    pub extra_fields: Vec<ExtraField>,
 }
 
 impl User {
     pub fn new(
-        user_id: &str,
+        user_id: UserId,
         email: &str,
         phone: &str,
         address: &str,
@@ -27,9 +52,12 @@ impl User {
         password_hash: &str,
         confirmed: bool,
         roles: Vec<&str>,
+        display_name: Option<&str>,
+        bio: Option<&str>,
+        extra_fields: Vec<ExtraField>,
     ) -> User {
         User {
-            user_id: user_id.to_string(),
+            user_id,
             email: email.to_string(),
             phone: phone.to_string(),
             address: address.to_string(),
@@ -37,6 +65,9 @@ impl User {
             password_hash: password_hash.to_string(),
             confirmed,
             roles: roles.par_iter().map(|&val| val.to_string()).collect(),
+            display_name: display_name.map(|val| val.to_string()),
+            bio: bio.map(|val| val.to_string()),
+            extra_fields,
         }
     }
 
@@ -46,42 +77,49 @@ impl User {
         phone: &str,
         address: &str,
         image_url: &str,
-        password_hash: &str,
+        password: &str,
         confirmed: bool,
         roles: Vec<&str>,
-    ) -> Result<User, &'static str> {
-        match Uuid::parse_str(user_id) {
-            Ok(uuid_id) => uuid_id,
-            Err(_) => return Err("UUID is not valid"),
-        };
+    ) -> Result<User, ServerError> {
+        let user_id: UserId = user_id.parse()?;
         match validate_email(email) {
             true => true,
-            false => return Err("Email is not valid"),
+            false => return Err(ServerError::InvalidEmail),
         };
         match validate_phone(phone) {
             true => true,
-            false => return Err("Phone is invalid"),
+            false => return Err(ServerError::InvalidPhone),
         };
         match Url::parse(image_url) {
             Ok(url) => url,
-            Err(_) => return Err("URL is not valid"),
+            Err(_) => return Err(ServerError::InvalidUrl),
         };
+        let password_hash = credentials::hash_password(password)?;
         Ok(User::new(
             user_id,
             email,
             phone,
             address,
             image_url,
-            password_hash,
+            &password_hash,
             confirmed,
             roles,
+            None,
+            None,
+            Vec::new(),
         ))
     }
 
-    pub fn update_email(&self, email: &str) -> Result<User, &'static str> {
+    /// Verifies a plaintext password candidate against this user's stored
+    /// Argon2id hash, in constant time.
+    pub fn verify_password(&self, candidate: &str) -> bool {
+        credentials::verify_password(&self.password_hash, candidate)
+    }
+
+    pub fn update_email(&self, email: &str) -> Result<User, ServerError> {
         match validate_email(email) {
             true => true,
-            false => return Err("Email is not valid"),
+            false => return Err(ServerError::InvalidEmail),
         };
         Ok(User {
             email: email.to_string(),
@@ -90,10 +128,10 @@ impl User {
     }
 
     // not tested functions
-    pub fn update_phone(&self, phone: &str) -> Result<User, &'static str> {
+    pub fn update_phone(&self, phone: &str) -> Result<User, ServerError> {
         match validate_phone(phone) {
             true => true,
-            false => return Err("Phone number is not valid"),
+            false => return Err(ServerError::InvalidPhone),
         };
         Ok(User {
             phone: phone.to_string(),
@@ -102,18 +140,60 @@ impl User {
     }
 
     // not tested functions
-    pub fn update_address(&self, address: &str) -> Result<User, &'static str> {
+    pub fn update_address(&self, address: &str) -> Result<User, ServerError> {
         Ok(User {
             address: address.to_string(),
             ..self.clone()
         })
     }
+
+    /// Validates and applies a profile update: a trimmed, length-bound
+    /// `display_name`, a trimmed, length-capped `bio`, and a bounded list
+    /// of custom `extra_fields`, each with a non-empty, length-capped key
+    /// and a length-capped value.
+    pub fn update_profile(
+        &self,
+        display_name: Option<&str>,
+        bio: Option<&str>,
+        extra_fields: Vec<ExtraField>,
+    ) -> Result<User, ServerError> {
+        let display_name = match display_name.map(str::trim) {
+            Some(name) if name.len() > 50 => return Err(ServerError::DisplayNameTooLong),
+            Some(name) => Some(name.to_string()),
+            None => None,
+        };
+        let bio = match bio.map(str::trim) {
+            Some(text) if text.len() > 280 => return Err(ServerError::BioTooLong),
+            Some(text) => Some(text.to_string()),
+            None => None,
+        };
+        if extra_fields.len() > 10 {
+            return Err(ServerError::TooManyExtraFields);
+        }
+        for field in &extra_fields {
+            let key = field.name.trim();
+            if key.is_empty() {
+                return Err(ServerError::ExtraFieldKeyEmpty);
+            } else if key.len() > 30 {
+                return Err(ServerError::ExtraFieldKeyTooLong);
+            }
+            if field.value.len() > 200 {
+                return Err(ServerError::ExtraFieldValueTooLong);
+            }
+        }
+        Ok(User {
+            display_name,
+            bio,
+            extra_fields,
+            ..self.clone()
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub struct Product {
-    pub product_id: String,  // UUID v4
-    pub business_id: String, // UUID v4
+    pub product_id: ProductId,
+    pub business_id: BusinessId,
     pub title: String,
     pub description: String,
     pub image_url: String,
@@ -123,28 +203,31 @@ pub struct Product {
     // to be used on the future for making a better formatted
     // version of price.
     pub unformatted_price: String,
+    pub currency: String, // ISO 4217 code, e.g. "BRL"
     pub product_tags: Vec<String>,
 }
 
 impl Product {
     pub fn new(
-        product_id: &str,
-        business_id: &str,
+        product_id: ProductId,
+        business_id: BusinessId,
         title: &str,
         description: &str,
         image_url: &str,
         price: &str,
         unformatted_price: &str,
+        currency: &str,
         product_tags: Vec<&str>,
     ) -> Product {
         Product {
-            product_id: product_id.to_string(),
-            business_id: business_id.to_string(),
+            product_id,
+            business_id,
             title: title.to_string(),
             description: description.to_string(),
             image_url: image_url.to_string(),
             price: price.to_string(),
             unformatted_price: unformatted_price.to_string(),
+            currency: currency.to_string(),
             product_tags: product_tags.par_iter().map(|&val| val.to_string()).collect(),
         }
     }
@@ -156,33 +239,30 @@ impl Product {
         description: &str,
         image_url: &str,
         unformatted_price: &str,
+        currency: &str,
         product_tags: Vec<&str>,
-    ) -> Result<Product, &'static str> {
-        match Uuid::parse_str(product_id) {
-            Ok(uuid_id) => uuid_id,
-            Err(_) => return Err("UUID is not valid"),
-        };
-        match Uuid::parse_str(business_id) {
-            Ok(uuid_id) => uuid_id,
-            Err(_) => return Err("Business UUID is not valid"),
-        };
+    ) -> Result<Product, ServerError> {
+        let product_id: ProductId = product_id.parse()?;
+        let business_id: BusinessId = business_id.parse()?;
         match Url::parse(image_url) {
             Ok(url) => url,
-            Err(_) => return Err("URL is not valid"),
+            Err(_) => return Err(ServerError::InvalidUrl),
         };
         if title.len() < 5 {
-            return Err("Too short title");
+            return Err(ServerError::TitleTooShort);
         } else if title.len() > 100 {
-            return Err("Too big title");
+            return Err(ServerError::TitleTooLong);
         }
         if description.len() < 5 {
-            return Err("Too short description");
-        } else if title.len() > 1000 {
-            return Err("Too big description");
+            return Err(ServerError::DescriptionTooShort);
+        } else if description.len() > 1000 {
+            return Err(ServerError::DescriptionTooLong);
         }
-        let price = Money::from_str(unformatted_price, iso::BRL)
-            .unwrap()
-            .to_string();
+        let resolved_currency = iso::find(currency).ok_or(ServerError::UnknownCurrency)?;
+        let price = match Money::from_str(unformatted_price, resolved_currency) {
+            Ok(money) => money.to_string(),
+            Err(_) => return Err(ServerError::PriceParse),
+        };
         Ok(Product::new(
             product_id,
             business_id,
@@ -191,6 +271,7 @@ impl Product {
             image_url,
             &price,
             unformatted_price,
+            resolved_currency.iso_alpha_code,
             product_tags,
         ))
     }
@@ -198,23 +279,23 @@ impl Product {
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub struct Business {
-    pub business_id: String,      // UUID v4
-    pub user_id: String,          // UUID v4
-    pub products_id: Vec<String>, // Vector of UUIDs v4
+    pub business_id: BusinessId,
+    pub user_id: UserId,
+    pub products_id: Vec<ProductId>,
     pub business_tags: Vec<String>,
 }
 
 impl Business {
     pub fn new(
-        business_id: &str,
-        user_id: &str,
-        products_id: Vec<&str>,
+        business_id: BusinessId,
+        user_id: UserId,
+        products_id: Vec<ProductId>,
         business_tags: Vec<&str>,
     ) -> Business {
         Business {
-            business_id: business_id.to_string(),
-            user_id: user_id.to_string(),
-            products_id: products_id.par_iter().map(|&val| val.to_string()).collect(),
+            business_id,
+            user_id,
+            products_id,
             business_tags: business_tags.par_iter().map(|&val| val.to_string()).collect(),
         }
     }
@@ -224,21 +305,13 @@ impl Business {
         user_id: &str,
         products_id: Vec<&str>,
         business_tags: Vec<&str>,
-    ) -> Result<Business, &'static str> {
-        match Uuid::parse_str(business_id) {
-            Ok(uuid_id) => uuid_id,
-            Err(_) => return Err("A ID is not a UUID"),
-        };
-        match Uuid::parse_str(user_id) {
-            Ok(uuid_id) => uuid_id,
-            Err(_) => return Err("A ID is not a UUID"),
-        };
-        for id in &products_id {
-            match Uuid::parse_str(id) {
-                Ok(uuid_id) => uuid_id,
-                Err(_) => return Err("A ID is not a UUID"),
-            };
-        }
+    ) -> Result<Business, ServerError> {
+        let business_id: BusinessId = business_id.parse()?;
+        let user_id: UserId = user_id.parse()?;
+        let products_id: Vec<ProductId> = products_id
+            .into_iter()
+            .map(|id| id.parse())
+            .collect::<Result<_, _>>()?;
         Ok(Business::new(
             business_id,
             user_id,
@@ -248,24 +321,22 @@ impl Business {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
-pub struct Order {
-    pub order_id: String,   // UUID v4
-    pub product_id: String, // UUID v4
-    pub ordered_date: String,
-    pub expected_date: String,
-}
-
 #[cfg(test)]
 mod tests {
     use super::Business;
+    use super::ExtraField;
     use super::Product;
     use super::User;
+    use super::{BusinessId, ProductId, UserId};
+
+    const A_UUID: &str = "25650673-c3e8-4cbb-a7bd-e27d268157b8";
+    const ANOTHER_UUID: &str = "3fa85f64-5717-4562-b3fc-2c963f66afa6";
 
     #[test]
     fn test_new_user() {
+        let user_id: UserId = A_UUID.parse().unwrap();
         let user = User::new(
-            "XD",
+            user_id,
             "email",
             "3091u2304",
             "dominguinhos",
@@ -273,10 +344,13 @@ mod tests {
             "XDXD",
             false,
             vec!["admin"],
+            None,
+            None,
+            Vec::new(),
         );
         assert_eq!(
             User {
-                user_id: "XD".to_string(),
+                user_id,
                 email: "email".to_string(),
                 phone: "3091u2304".to_string(),
                 address: "dominguinhos".to_string(),
@@ -284,6 +358,9 @@ mod tests {
                 password_hash: "XDXD".to_string(),
                 confirmed: false,
                 roles: vec!["admin".to_string()],
+                display_name: None,
+                bio: None,
+                extra_fields: Vec::new(),
             },
             user,
             "User new function is working correctly"
@@ -323,28 +400,83 @@ mod tests {
         assert!(updated_user.is_ok(), "User email updated successfully")
     }
 
+    #[test]
+    fn update_profile_user() {
+        let user = User::create(
+            "25650673-c3e8-4cbb-a7bd-e27d268157b8",
+            "email@example.com",
+            "+5521965237969",
+            "Rua Dominguinhos",
+            "http://image.url.com",
+            "XDXD",
+            false,
+            vec!["admin"],
+        )
+        .unwrap();
+        let updated_user = user.update_profile(
+            Some(" Dominguinhos "),
+            Some("risoto enjoyer"),
+            vec![ExtraField {
+                name: "favorite dish".to_string(),
+                value: "risoto".to_string(),
+            }],
+        );
+        assert!(updated_user.is_ok(), "User profile updated successfully");
+        let updated_user = updated_user.unwrap();
+        assert_eq!(updated_user.display_name, Some("Dominguinhos".to_string()));
+        assert_eq!(updated_user.bio, Some("risoto enjoyer".to_string()));
+    }
+
+    #[test]
+    fn update_profile_user_rejects_empty_extra_field_key() {
+        let user = User::create(
+            "25650673-c3e8-4cbb-a7bd-e27d268157b8",
+            "email@example.com",
+            "+5521965237969",
+            "Rua Dominguinhos",
+            "http://image.url.com",
+            "XDXD",
+            false,
+            vec!["admin"],
+        )
+        .unwrap();
+        let updated_user = user.update_profile(
+            None,
+            None,
+            vec![ExtraField {
+                name: "  ".to_string(),
+                value: "risoto".to_string(),
+            }],
+        );
+        assert!(updated_user.is_err(), "empty extra field key is rejected");
+    }
+
     #[test]
     fn test_new_product() {
+        let product_id: ProductId = A_UUID.parse().unwrap();
+        let business_id: BusinessId = ANOTHER_UUID.parse().unwrap();
         let product = Product::new(
-            "09320481032",
-            "102938409128305",
+            product_id,
+            business_id,
             "aiowjefaieorf",
             "oiewroaer",
             "oijeaij",
             "2000",
             "2000",
+            "BRL",
             vec!["oaiewjf"],
         );
         assert_eq!(
             product,
             Product {
-                product_id: "09320481032".to_string(),
-                business_id: "102938409128305".to_string(),
+                product_id,
+                business_id,
                 title: "aiowjefaieorf".to_string(),
                 description: "oiewroaer".to_string(),
                 image_url: "oijeaij".to_string(),
                 price: "2000".to_string(),
                 unformatted_price: "2000".to_string(),
+                currency: "BRL".to_string(),
                 product_tags: vec!["oaiewjf".to_string()]
             }
         );
@@ -359,6 +491,7 @@ mod tests {
             "oaiejriearg",
             "http://image.com",
             "2000",
+            "BRL",
             vec!["fiejroa"],
         ).is_ok();
         assert!(product, "Product create function is working correctly");
@@ -366,18 +499,21 @@ mod tests {
 
     #[test]
     fn test_new_business() {
+        let business_id: BusinessId = A_UUID.parse().unwrap();
+        let user_id: UserId = ANOTHER_UUID.parse().unwrap();
+        let product_id: ProductId = A_UUID.parse().unwrap();
         let business = Business::new(
-            "25650673-c3e8-4cbb-a7bd-e27d268157b8",
-            "1029340918",
-            vec!["01239041"],
+            business_id,
+            user_id,
+            vec![product_id],
             vec!["hamburguer"],
         );
         assert_eq!(
             business,
             Business {
-                business_id: "25650673-c3e8-4cbb-a7bd-e27d268157b8".to_string(),
-                user_id: "1029340918".to_string(),
-                products_id: vec!["01239041".to_string()],
+                business_id,
+                user_id,
+                products_id: vec![product_id],
                 business_tags: vec!["hamburguer".to_string()],
             }
         );
@@ -386,9 +522,9 @@ mod tests {
     #[test]
     fn test_create_business() {
         let business = Business::create(
-            "25650673-c3e8-4cbb-a7bd-e27d268157b8",
-            "25650673-c3e8-4cbb-a7bd-e27d268157b8",
-            vec!["25650673-c3e8-4cbb-a7bd-e27d268157b8"],
+            A_UUID,
+            A_UUID,
+            vec![A_UUID],
             vec!["hamburguer"],
         )
         .is_ok();
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{OrderId, ProductId, ServerError};
+
+/// Where an `Order` sits in its fulfillment lifecycle.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum OrderStatus {
+    #[default]
+    Placed,
+    Confirmed,
+    Preparing,
+    Dispatched,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderStatus {
+    fn can_transition_to(self, to: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, to),
+            (Placed, Confirmed)
+                | (Placed, Cancelled)
+                | (Confirmed, Preparing)
+                | (Confirmed, Cancelled)
+                | (Preparing, Dispatched)
+                | (Preparing, Cancelled)
+                | (Dispatched, Delivered)
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            OrderStatus::Placed => "placed",
+            OrderStatus::Confirmed => "confirmed",
+            OrderStatus::Preparing => "preparing",
+            OrderStatus::Dispatched => "dispatched",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+        };
+        write!(f, "{label}")
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = ServerError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "placed" => Ok(OrderStatus::Placed),
+            "confirmed" => Ok(OrderStatus::Confirmed),
+            "preparing" => Ok(OrderStatus::Preparing),
+            "dispatched" => Ok(OrderStatus::Dispatched),
+            "delivered" => Ok(OrderStatus::Delivered),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            _ => Err(ServerError::InvalidOrderStatus),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Order {
+    pub order_id: OrderId,
+    pub product_id: ProductId,
+    pub ordered_date: String, // RFC 3339
+    pub expected_date: String, // RFC 3339
+    pub status: OrderStatus,
+}
+
+impl Order {
+    pub fn new(
+        order_id: OrderId,
+        product_id: ProductId,
+        ordered_date: &str,
+        expected_date: &str,
+        status: OrderStatus,
+    ) -> Order {
+        Order {
+            order_id,
+            product_id,
+            ordered_date: ordered_date.to_string(),
+            expected_date: expected_date.to_string(),
+            status,
+        }
+    }
+
+    pub fn create(
+        order_id: &str,
+        product_id: &str,
+        ordered_date: &str,
+        expected_date: &str,
+    ) -> Result<Order, ServerError> {
+        let order_id: OrderId = order_id.parse()?;
+        let product_id: ProductId = product_id.parse()?;
+        let ordered_at =
+            DateTime::parse_from_rfc3339(ordered_date).map_err(|_| ServerError::InvalidDate)?;
+        let expected_at =
+            DateTime::parse_from_rfc3339(expected_date).map_err(|_| ServerError::InvalidDate)?;
+        if expected_at < ordered_at {
+            return Err(ServerError::ExpectedDateBeforeOrderedDate);
+        }
+        Ok(Order::new(
+            order_id,
+            product_id,
+            ordered_date,
+            expected_date,
+            OrderStatus::Placed,
+        ))
+    }
+
+    /// Moves the order to `to`, rejecting anything that isn't a legal
+    /// lifecycle transition from the current status.
+    pub fn transition(&self, to: OrderStatus) -> Result<Order, ServerError> {
+        if !self.status.can_transition_to(to) {
+            return Err(ServerError::IllegalOrderTransition);
+        }
+        Ok(Order {
+            status: to,
+            ..self.clone()
+        })
+    }
+}
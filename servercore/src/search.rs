@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::{Business, Product, ProductId};
+
+/// A pluggable free-text index over products' title/description/tags (and,
+/// transitively, the business that owns them).
+///
+/// `InMemorySearchIndex` is the default, dependency-free backend; swapping in
+/// something like Meilisearch or Tantivy just means implementing this trait.
+pub trait SearchIndex: Send + Sync {
+    fn index_product(&self, product: &Product);
+    fn index_business(&self, business: &Business);
+    fn remove_product(&self, product_id: &ProductId);
+    /// Returns matching product ids ranked by relevance, most relevant first.
+    fn search_products(&self, query: &str, tags_filter: &[String], limit: usize) -> Vec<ProductId>;
+}
+
+struct ProductDocument {
+    text: String,
+    tags: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct InMemorySearchIndex {
+    products: RwLock<HashMap<ProductId, ProductDocument>>,
+    business_tags: RwLock<HashMap<ProductId, Vec<String>>>,
+}
+
+impl SearchIndex for InMemorySearchIndex {
+    fn index_product(&self, product: &Product) {
+        let text = format!("{} {}", product.title, product.description).to_lowercase();
+        let tags = product
+            .product_tags
+            .iter()
+            .map(|tag| tag.to_lowercase())
+            .collect();
+        self.products
+            .write()
+            .unwrap()
+            .insert(product.product_id, ProductDocument { text, tags });
+    }
+
+    fn index_business(&self, business: &Business) {
+        let tags: Vec<String> = business
+            .business_tags
+            .iter()
+            .map(|tag| tag.to_lowercase())
+            .collect();
+        let mut business_tags = self.business_tags.write().unwrap();
+        for product_id in &business.products_id {
+            business_tags.insert(*product_id, tags.clone());
+        }
+    }
+
+    fn remove_product(&self, product_id: &ProductId) {
+        self.products.write().unwrap().remove(product_id);
+        self.business_tags.write().unwrap().remove(product_id);
+    }
+
+    fn search_products(&self, query: &str, tags_filter: &[String], limit: usize) -> Vec<ProductId> {
+        let query = query.to_lowercase();
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        let tags_filter: Vec<String> = tags_filter.iter().map(|tag| tag.to_lowercase()).collect();
+        let products = self.products.read().unwrap();
+        let business_tags = self.business_tags.read().unwrap();
+
+        let mut ranked: Vec<(ProductId, usize)> = products
+            .iter()
+            .filter_map(|(product_id, doc)| {
+                let inherited_tags = business_tags.get(product_id);
+                let has_all_tags = tags_filter.iter().all(|tag| {
+                    doc.tags.contains(tag)
+                        || inherited_tags.is_some_and(|tags| tags.contains(tag))
+                });
+                if !has_all_tags {
+                    return None;
+                }
+                let score = terms.iter().filter(|term| doc.text.contains(*term)).count();
+                if terms.is_empty() || score > 0 {
+                    Some((*product_id, score))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1));
+        ranked.truncate(limit);
+        ranked.into_iter().map(|(product_id, _)| product_id).collect()
+    }
+}
@@ -0,0 +1,55 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::ServerError;
+
+/// Claims carried by a session JWT issued after a successful login.
+///
+/// `roles` mirrors `User::roles` so protected RPCs can authorize a request
+/// without a second lookup.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Claims {
+    pub user_id: String,
+    pub roles: Vec<String>,
+    pub iat: usize,
+    pub exp: usize,
+    pub iss: String,
+}
+
+/// Signs a new session token for `user_id`/`roles`, valid for `ttl` from now.
+pub fn issue_token(
+    user_id: &str,
+    roles: &[String],
+    issuer: &str,
+    secret: &[u8],
+    ttl: Duration,
+) -> Result<String, ServerError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| ServerError::TokenEncode)?;
+    let claims = Claims {
+        user_id: user_id.to_string(),
+        roles: roles.to_vec(),
+        iat: now.as_secs() as usize,
+        exp: (now + ttl).as_secs() as usize,
+        iss: issuer.to_string(),
+    };
+    encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(secret),
+    )
+    .map_err(|_| ServerError::TokenEncode)
+}
+
+/// Verifies a compact-serialized JWT's signature, expiry and issuer,
+/// returning its claims on success.
+pub fn verify_token(token: &str, issuer: &str, secret: &[u8]) -> Result<Claims, ServerError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_issuer(&[issuer]);
+    decode::<Claims>(token, &DecodingKey::from_secret(secret), &validation)
+        .map(|data| data.claims)
+        .map_err(|_| ServerError::InvalidToken)
+}
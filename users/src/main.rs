@@ -1,15 +1,101 @@
 use servercore::Business;
-use servercore::User;
-use tonic::{transport::Server, Code, Request, Response, Status};
+use servercore::{issue_confirmation_token, ConfirmationToken};
+use servercore::{issue_token, verify_token, Claims, ExtraField, ServerError, User};
+use servercore::{InMemorySearchIndex, Product, SearchIndex};
+use servercore::{Order, OrderStatus};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tonic::metadata::MetadataMap;
+use tonic::{transport::Server, Request, Response, Status};
+use users::order_service_server::{OrderService, OrderServiceServer};
+use users::product_service_server::{ProductService, ProductServiceServer};
 use users::user_service_server::{UserService, UserServiceServer};
-use users::{BusinessData, BusinessResponseData, UserData, UserResponseData};
+use users::{
+    BusinessData, BusinessResponseData, ConfirmEmailData, ExtraFieldData, LoginData,
+    LoginResponseData, OrderData, OrderResponseData, ProductData, ProductResponseData,
+    RequestConfirmationData, RequestConfirmationResponseData, SearchProductsData,
+    SearchProductsResponseData, UpdateOrderStatusData, UpdateProfileData, UserData,
+    UserResponseData,
+};
 use uuid::Uuid;
 pub mod users {
     tonic::include_proto!("users");
 }
 
-#[derive(Default)]
-struct UserGRPCData {}
+const TOKEN_ISSUER: &str = "risotoexperience-users";
+const TOKEN_SECRET: &[u8] = b"change-me-in-production";
+const TOKEN_TTL: Duration = Duration::from_secs(60 * 60);
+const CONFIRMATION_TOKEN_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Parses and verifies the `authorization: Bearer <token>` metadata on a
+/// request, for RPCs that require an authenticated caller.
+fn authorize(metadata: &MetadataMap) -> Result<Claims, Status> {
+    let token = metadata
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("missing bearer token"))?;
+    verify_token(token, TOKEN_ISSUER, TOKEN_SECRET).map_err(Status::from)
+}
+
+/// Checks that `claims` carries `role`, for RPCs restricted to a specific
+/// kind of account (e.g. only businesses may list products).
+fn require_role(claims: &Claims, role: &'static str) -> Result<(), Status> {
+    if claims.roles.iter().any(|claimed| claimed == role) {
+        Ok(())
+    } else {
+        Err(ServerError::MissingRole(role).into())
+    }
+}
+
+// Keyed by email. A real deployment would back this with a database; this
+// crate has none yet, so an in-memory store stands in for it.
+struct UserGRPCData {
+    users: RwLock<HashMap<String, User>>,
+    // Keyed by user_id.
+    confirmation_tokens: RwLock<HashMap<String, ConfirmationToken>>,
+    // Keyed by business_id. Shared with `ProductGRPCData` so a product
+    // created for a business can be linked back onto it.
+    businesses: Arc<RwLock<HashMap<String, Business>>>,
+    // Shared with `ProductGRPCData` so `create_business` can index a
+    // business's tags alongside its products.
+    search_index: Arc<InMemorySearchIndex>,
+}
+
+impl UserGRPCData {
+    /// Issues a fresh confirmation token for `user_id`, storing it in place
+    /// of (and thereby invalidating) any previous one.
+    fn issue_confirmation(&self, user_id: &str) -> String {
+        let issued = issue_confirmation_token(CONFIRMATION_TOKEN_TTL);
+        self.confirmation_tokens
+            .write()
+            .unwrap()
+            .insert(user_id.to_string(), issued.record);
+        issued.token
+    }
+}
+
+fn user_response(user: &User) -> UserResponseData {
+    UserResponseData {
+        user_id: user.user_id.to_string(),
+        email: user.email.clone(),
+        phone: user.phone.clone(),
+        address: user.address.clone(),
+        image_url: user.image_url.clone(),
+        roles: user.roles.clone(),
+        display_name: user.display_name.clone(),
+        bio: user.bio.clone(),
+        extra_fields: user
+            .extra_fields
+            .iter()
+            .map(|field| ExtraFieldData {
+                name: field.name.clone(),
+                value: field.value.clone(),
+            })
+            .collect(),
+    }
+}
 
 #[tonic::async_trait]
 impl UserService for UserGRPCData {
@@ -27,23 +113,21 @@ impl UserService for UserGRPCData {
             &Uuid::new_v4().to_string(),
             email,
             phone,
-            password,
-            image_url,
             address,
+            image_url,
+            password,
             false,
             roles,
         ) {
             Ok(user) => user,
-            Err(e) => return Err(Status::new(Code::InvalidArgument, e)),
+            Err(e) => return Err(e.into()),
         };
-        Ok(Response::new(UserResponseData {
-            user_id: user.user_id,
-            email: user.email,
-            phone: user.phone,
-            address: user.address,
-            image_url: user.image_url,
-            roles: vec!["user".to_string()],
-        }))
+        self.users
+            .write()
+            .unwrap()
+            .insert(user.email.clone(), user.clone());
+        self.issue_confirmation(&user.user_id.to_string());
+        Ok(Response::new(user_response(&user)))
     }
 
     async fn create_business(
@@ -62,15 +146,20 @@ impl UserService for UserGRPCData {
             user_id,
             email,
             phone,
-            password,
-            image_url,
             address,
+            image_url,
+            password,
             false,
             roles,
         ) {
             Ok(user) => user,
-            Err(e) => return Err(Status::new(Code::InvalidArgument, e)),
+            Err(e) => return Err(e.into()),
         };
+        self.users
+            .write()
+            .unwrap()
+            .insert(user.email.clone(), user.clone());
+        self.issue_confirmation(&user.user_id.to_string());
         let business = match Business::create(
             business_id,
             user_id,
@@ -78,11 +167,16 @@ impl UserService for UserGRPCData {
             Vec::default(),
         ) {
             Ok(business) => business,
-            Err(e) => return Err(Status::new(Code::InvalidArgument, e)),
+            Err(e) => return Err(e.into()),
         };
+        self.search_index.index_business(&business);
+        self.businesses
+            .write()
+            .unwrap()
+            .insert(business.business_id.to_string(), business.clone());
         Ok(Response::new(BusinessResponseData {
-            user_id: business.user_id,
-            business_id: business.business_id,
+            user_id: business.user_id.to_string(),
+            business_id: business.business_id.to_string(),
             email: user.email,
             phone: user.phone,
             address: user.address,
@@ -90,16 +184,287 @@ impl UserService for UserGRPCData {
             roles: user.roles,
         }))
     }
+
+    async fn login(
+        &self,
+        request: Request<LoginData>,
+    ) -> Result<Response<LoginResponseData>, Status> {
+        let email = &request.get_ref().email;
+        let password = &request.get_ref().password;
+        let users = self.users.read().unwrap();
+        let user = users
+            .get(email)
+            .ok_or(ServerError::NotFound("user"))?;
+        if !user.verify_password(password) {
+            return Err(ServerError::InvalidCredentials.into());
+        }
+        let token = issue_token(
+            &user.user_id.to_string(),
+            &user.roles,
+            TOKEN_ISSUER,
+            TOKEN_SECRET,
+            TOKEN_TTL,
+        )?;
+        Ok(Response::new(LoginResponseData {
+            user_id: user.user_id.to_string(),
+            email: user.email.clone(),
+            phone: user.phone.clone(),
+            address: user.address.clone(),
+            image_url: user.image_url.clone(),
+            roles: user.roles.clone(),
+            token,
+        }))
+    }
+
+    async fn request_confirmation(
+        &self,
+        request: Request<RequestConfirmationData>,
+    ) -> Result<Response<RequestConfirmationResponseData>, Status> {
+        let user_id = &request.get_ref().user_id;
+        let known = self
+            .users
+            .read()
+            .unwrap()
+            .values()
+            .any(|user| &user.user_id.to_string() == user_id);
+        if !known {
+            return Err(ServerError::NotFound("user").into());
+        }
+        let token = self.issue_confirmation(user_id);
+        Ok(Response::new(RequestConfirmationResponseData { token }))
+    }
+
+    async fn confirm_email(
+        &self,
+        request: Request<ConfirmEmailData>,
+    ) -> Result<Response<UserResponseData>, Status> {
+        let user_id = &request.get_ref().user_id;
+        let token = &request.get_ref().token;
+        {
+            let tokens = self.confirmation_tokens.read().unwrap();
+            let record = tokens
+                .get(user_id)
+                .ok_or(ServerError::NotFound("confirmation token"))?;
+            record.verify(token)?;
+        }
+        self.confirmation_tokens.write().unwrap().remove(user_id);
+        let mut users = self.users.write().unwrap();
+        let user = users
+            .values_mut()
+            .find(|user| &user.user_id.to_string() == user_id)
+            .ok_or(ServerError::NotFound("user"))?;
+        user.confirmed = true;
+        Ok(Response::new(user_response(user)))
+    }
+
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileData>,
+    ) -> Result<Response<UserResponseData>, Status> {
+        let claims = authorize(request.metadata())?;
+        let display_name = request.get_ref().display_name.as_deref();
+        let bio = request.get_ref().bio.as_deref();
+        let extra_fields: Vec<ExtraField> = request
+            .get_ref()
+            .extra_fields
+            .iter()
+            .map(|field| ExtraField {
+                name: field.name.clone(),
+                value: field.value.clone(),
+            })
+            .collect();
+        let mut users = self.users.write().unwrap();
+        let user = users
+            .values_mut()
+            .find(|user| user.user_id.to_string() == claims.user_id)
+            .ok_or(ServerError::NotFound("user"))?;
+        let updated = user.update_profile(display_name, bio, extra_fields)?;
+        *user = updated;
+        Ok(Response::new(user_response(user)))
+    }
+}
+
+// Keyed by product_id. Same caveat as UserGRPCData: stands in for a database.
+struct ProductGRPCData {
+    products: RwLock<HashMap<String, Product>>,
+    search_index: Arc<InMemorySearchIndex>,
+    // Keyed by business_id. Shared with `UserGRPCData` so a new product can
+    // be linked onto its business and the business's tags re-indexed.
+    businesses: Arc<RwLock<HashMap<String, Business>>>,
+}
+
+#[tonic::async_trait]
+impl ProductService for ProductGRPCData {
+    async fn create_product(
+        &self,
+        request: Request<ProductData>,
+    ) -> Result<Response<ProductResponseData>, Status> {
+        let claims = authorize(request.metadata())?;
+        require_role(&claims, "business")?;
+        let business_id = &request.get_ref().business_id;
+        let title = &request.get_ref().title;
+        let description = &request.get_ref().description;
+        let image_url = &request.get_ref().image_url;
+        let unformatted_price = &request.get_ref().unformatted_price;
+        let currency = &request.get_ref().currency;
+        let product_tags: Vec<&str> = request
+            .get_ref()
+            .product_tags
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let product = match Product::create(
+            &Uuid::new_v4().to_string(),
+            business_id,
+            title,
+            description,
+            image_url,
+            unformatted_price,
+            currency,
+            product_tags,
+        ) {
+            Ok(product) => product,
+            Err(e) => return Err(e.into()),
+        };
+        self.search_index.index_product(&product);
+        if let Some(business) = self
+            .businesses
+            .write()
+            .unwrap()
+            .get_mut(&business_id.to_string())
+        {
+            business.products_id.push(product.product_id);
+            self.search_index.index_business(business);
+        }
+        self.products
+            .write()
+            .unwrap()
+            .insert(product.product_id.to_string(), product.clone());
+        Ok(Response::new(ProductResponseData {
+            product_id: product.product_id.to_string(),
+            business_id: product.business_id.to_string(),
+            title: product.title,
+            description: product.description,
+            image_url: product.image_url,
+            price: product.price,
+            unformatted_price: product.unformatted_price,
+            currency: product.currency,
+            product_tags: product.product_tags,
+        }))
+    }
+
+    async fn search_products(
+        &self,
+        request: Request<SearchProductsData>,
+    ) -> Result<Response<SearchProductsResponseData>, Status> {
+        let query = &request.get_ref().query;
+        let tags_filter = &request.get_ref().tags_filter;
+        let limit = request.get_ref().limit as usize;
+        let hits = self.search_index.search_products(query, tags_filter, limit);
+        let products = self.products.read().unwrap();
+        let results = hits
+            .into_iter()
+            .filter_map(|product_id| products.get(&product_id.to_string()))
+            .map(|product| ProductResponseData {
+                product_id: product.product_id.to_string(),
+                business_id: product.business_id.to_string(),
+                title: product.title.clone(),
+                description: product.description.clone(),
+                image_url: product.image_url.clone(),
+                price: product.price.clone(),
+                unformatted_price: product.unformatted_price.clone(),
+                currency: product.currency.clone(),
+                product_tags: product.product_tags.clone(),
+            })
+            .collect();
+        Ok(Response::new(SearchProductsResponseData { products: results }))
+    }
+}
+
+// Keyed by order_id. Same caveat as UserGRPCData: stands in for a database.
+#[derive(Default)]
+struct OrderGRPCData {
+    orders: RwLock<HashMap<String, Order>>,
+}
+
+fn order_response(order: &Order) -> OrderResponseData {
+    OrderResponseData {
+        order_id: order.order_id.to_string(),
+        product_id: order.product_id.to_string(),
+        ordered_date: order.ordered_date.clone(),
+        expected_date: order.expected_date.clone(),
+        status: order.status.to_string(),
+    }
+}
+
+#[tonic::async_trait]
+impl OrderService for OrderGRPCData {
+    async fn create_order(
+        &self,
+        request: Request<OrderData>,
+    ) -> Result<Response<OrderResponseData>, Status> {
+        let claims = authorize(request.metadata())?;
+        require_role(&claims, "business")?;
+        let product_id = &request.get_ref().product_id;
+        let ordered_date = &request.get_ref().ordered_date;
+        let expected_date = &request.get_ref().expected_date;
+        let order = match Order::create(
+            &Uuid::new_v4().to_string(),
+            product_id,
+            ordered_date,
+            expected_date,
+        ) {
+            Ok(order) => order,
+            Err(e) => return Err(e.into()),
+        };
+        self.orders
+            .write()
+            .unwrap()
+            .insert(order.order_id.to_string(), order.clone());
+        Ok(Response::new(order_response(&order)))
+    }
+
+    async fn update_order_status(
+        &self,
+        request: Request<UpdateOrderStatusData>,
+    ) -> Result<Response<OrderResponseData>, Status> {
+        let claims = authorize(request.metadata())?;
+        require_role(&claims, "business")?;
+        let order_id = &request.get_ref().order_id;
+        let status: OrderStatus = request.get_ref().status.parse()?;
+        let mut orders = self.orders.write().unwrap();
+        let order = orders
+            .get(order_id)
+            .ok_or(ServerError::NotFound("order"))?;
+        let updated = order.transition(status)?;
+        orders.insert(order_id.clone(), updated.clone());
+        Ok(Response::new(order_response(&updated)))
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr = "[::1]:50051".parse().unwrap();
-    let user_service = UserGRPCData::default();
+    let search_index = Arc::new(InMemorySearchIndex::default());
+    let businesses = Arc::new(RwLock::new(HashMap::new()));
+    let user_service = UserGRPCData {
+        users: RwLock::new(HashMap::new()),
+        confirmation_tokens: RwLock::new(HashMap::new()),
+        businesses: Arc::clone(&businesses),
+        search_index: Arc::clone(&search_index),
+    };
+    let product_service = ProductGRPCData {
+        products: RwLock::new(HashMap::new()),
+        search_index: Arc::clone(&search_index),
+        businesses: Arc::clone(&businesses),
+    };
+    let order_service = OrderGRPCData::default();
     println!("User service listening on {}", addr);
 
     Server::builder()
         .add_service(UserServiceServer::new(user_service))
+        .add_service(ProductServiceServer::new(product_service))
+        .add_service(OrderServiceServer::new(order_service))
         .serve(addr)
         .await?;
 